@@ -1,12 +1,24 @@
 use futures::{AsyncRead, AsyncWrite};
-use http_body_util::Empty;
-use hyper::{body::Bytes, Request, StatusCode};
+use http_body_util::{BodyExt, Empty, Full};
+use hyper::{
+    body::Bytes,
+    header::{HeaderName, HeaderValue},
+    HeaderMap, Request, StatusCode,
+};
 use hyper_util::rt::TokioIo;
 use k256::{pkcs8::DecodePrivateKey, SecretKey};
+use serde::{Deserialize, Serialize};
 use tlsn_common::config::ProtocolConfig;
 use tlsn_common::config::ProtocolConfigValidator;
-use tlsn_core::{attestation::AttestationConfig, signing::SignatureAlgId, CryptoProvider};
-use tlsn_core::{request::RequestConfig, transcript::TranscriptCommitConfig};
+use tlsn_core::{
+    attestation::{Attestation, AttestationConfig},
+    signing::{SignatureAlgId, VerifyingKey},
+    CryptoProvider, Secrets,
+};
+use tlsn_core::{
+    request::RequestConfig,
+    transcript::{Idx, TranscriptCommitConfig},
+};
 use tlsn_formats::http::{DefaultHttpCommitter, HttpCommit, HttpTranscript};
 use tlsn_prover::{Prover, ProverConfig};
 use tlsn_verifier::{Verifier, VerifierConfig};
@@ -19,7 +31,129 @@ const MAX_SENT_DATA: usize = 1 << 12;
 // Maximum number of bytes that can be received by prover from server
 const MAX_RECV_DATA: usize = 1 << 14;
 
+/// A duplex byte stream suitable for running the MPC-TLS protocol over,
+/// whether it came from an in-process [`tokio::io::duplex`] or a socket to a
+/// remote notary.
+trait NotaryIo: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin {}
+impl<T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Send + Unpin> NotaryIo for T {}
+
+/// The kind of connection the prover will run the MPC-TLS session over. The
+/// notary server needs this up front to know how the prover will connect.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+enum ClientType {
+    /// A direct TCP connection between prover and notary.
+    Tcp,
+}
+
+/// Body of the `POST /session` request sent to a remote notary server to
+/// negotiate the protocol limits for the upcoming notarization. Field names
+/// match the standard `notary-server`'s API, which expects camelCase JSON.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NotarizationSessionRequest {
+    client_type: ClientType,
+    max_sent_data: usize,
+    max_recv_data: usize,
+}
+
+/// Response to a `POST /session` request, carrying the id the prover must
+/// present when it reconnects to run the actual MPC-TLS session.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotarizationSessionResponse {
+    session_id: String,
+}
+
+/// Names the request/response headers whose values, and the body substrings,
+/// that must stay hidden from the revealed presentation, e.g. `Authorization`,
+/// session cookies, or a bearer token embedded in a JSON body. Everything not
+/// named here is revealed, including the names of redacted headers;
+/// everything (named or not) is still committed to.
+#[derive(Debug, Default, Clone)]
+pub struct RedactionSpec {
+    /// Request header field names whose values are redacted, matched
+    /// case-insensitively. The header name itself stays revealed.
+    pub sent_headers: Vec<String>,
+    /// Response header field names whose values are redacted, matched
+    /// case-insensitively. The header name itself stays revealed.
+    pub recv_headers: Vec<String>,
+    /// Substrings to redact wherever they occur in the sent bytes.
+    pub sent_body_substrings: Vec<String>,
+    /// Substrings to redact wherever they occur in the received bytes.
+    pub recv_body_substrings: Vec<String>,
+}
+
+/// Finds every occurrence of `needles` in `haystack` and returns their byte
+/// ranges as an [`Idx`].
+fn find_substrings(haystack: &[u8], needles: &[String]) -> Idx {
+    let mut ranges = Vec::new();
+    for needle in needles {
+        let needle = needle.as_bytes();
+        if needle.is_empty() {
+            continue;
+        }
+
+        let mut start = 0;
+        while start + needle.len() <= haystack.len() {
+            match haystack[start..].windows(needle.len()).position(|w| w == needle) {
+                Some(pos) => {
+                    let begin = start + pos;
+                    ranges.push(begin..begin + needle.len());
+                    start = begin + needle.len();
+                }
+                None => break,
+            }
+        }
+    }
+    Idx::new(ranges)
+}
+
+/// Resolves a [`RedactionSpec`] against the parsed HTTP transcript and raw
+/// transcript bytes, returning the sent- and received-side [`Idx`] ranges
+/// that must be hidden from the revealed presentation.
+fn redacted_ranges(
+    transcript: &HttpTranscript,
+    sent_bytes: &[u8],
+    recv_bytes: &[u8],
+    redact: &RedactionSpec,
+) -> (Idx, Idx) {
+    let mut sent = Idx::default();
+    for header in &transcript.request.headers {
+        if redact
+            .sent_headers
+            .iter()
+            .any(|name| header.name.as_str().eq_ignore_ascii_case(name))
+        {
+            sent = sent.union(&Idx::new(header.value.span().indices()));
+        }
+    }
+    sent = sent.union(&find_substrings(sent_bytes, &redact.sent_body_substrings));
+
+    let mut recv = Idx::default();
+    for response in &transcript.response {
+        for header in &response.headers {
+            if redact
+                .recv_headers
+                .iter()
+                .any(|name| header.name.as_str().eq_ignore_ascii_case(name))
+            {
+                recv = recv.union(&Idx::new(header.value.span().indices()));
+            }
+        }
+    }
+    recv = recv.union(&find_substrings(recv_bytes, &redact.recv_body_substrings));
+
+    (sent, recv)
+}
+
 /// Runs a simple Notary with the provided connection to the Prover.
+///
+/// This is an opt-in local fallback for development and testing: it runs the
+/// notary in-process, so it does not produce a meaningful attestation (the
+/// prover and the notary share the same trust domain). Prefer
+/// [`prove_with_notary`] against a real, independently-operated notary
+/// server.
 pub async fn run_notary<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(conn: T) {
     let pem_data = include_str!("notary.key");
     let secret_key = SecretKey::from_pkcs8_pem(pem_data).unwrap().to_bytes();
@@ -55,31 +189,274 @@ pub async fn run_notary<T: AsyncWrite + AsyncRead + Send + Unpin + 'static>(conn
 // Setting of the application server
 const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/114.0.0.0 Safari/537.36";
 
+// Protocol limit the prover negotiates with the notary for the response side.
+// We must configure the amount of data we expect to exchange beforehand,
+// which will be preprocessed prior to the connection. Reducing this limit
+// will improve performance. The sent-side limit is instead sized dynamically
+// from the request being notarized; see `estimated_request_len`.
+const PROVER_MAX_RECV_DATA: usize = 4096;
+
 #[tokio::main]
-pub async fn prove(domain: String, uri: String) -> Result<(), Box<dyn std::error::Error>> {
+#[allow(clippy::too_many_arguments)]
+pub async fn prove(
+    domain: String,
+    uri: String,
+    method: String,
+    headers: std::collections::HashMap<String, String>,
+    body: Option<Vec<u8>>,
+    redact: RedactionSpec,
+    defer_decryption: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt::init();
 
     let (prover_socket, notary_socket) = tokio::io::duplex(1 << 16);
 
     // Start a local simple notary service
     tokio::spawn(run_notary(notary_socket.compat()));
+
+    run_prover(
+        domain,
+        uri,
+        method,
+        headers,
+        body,
+        prover_socket.compat(),
+        redact,
+        defer_decryption,
+    )
+    .await
+}
+
+/// Notarizes `uri` on `domain` using a remote notary server reachable at
+/// `notary_host:notary_port`, instead of spawning one in-process.
+///
+/// This performs the notary server's session handshake: it `POST`s the
+/// agreed protocol limits to `/session` to obtain a session id, then
+/// reconnects and upgrades that connection into the raw MPC-TLS byte stream
+/// the returned session id authorizes.
+#[tokio::main]
+#[allow(clippy::too_many_arguments)]
+pub async fn prove_with_notary(
+    domain: String,
+    uri: String,
+    method: String,
+    headers: std::collections::HashMap<String, String>,
+    body: Option<Vec<u8>>,
+    notary_host: String,
+    notary_port: u16,
+    notary_tls: bool,
+    redact: RedactionSpec,
+    defer_decryption: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let max_sent_data = estimated_request_len(&method, &uri, &domain, &headers, body.as_deref());
+
+    let notary_socket = notary_connect(
+        &notary_host,
+        notary_port,
+        notary_tls,
+        max_sent_data,
+        PROVER_MAX_RECV_DATA,
+    )
+    .await?;
+
+    run_prover(
+        domain,
+        uri,
+        method,
+        headers,
+        body,
+        notary_socket.compat(),
+        redact,
+        defer_decryption,
+    )
+    .await
+}
+
+/// Builds the header set for a notarized request: defaults every
+/// notarization needs, overridden by any headers the caller supplied.
+/// `Accept-Encoding` is always forced to `identity` and is not
+/// caller-overridable, since the transcript committer can't handle a
+/// compressed response.
+fn build_headers(domain: &str, extra: &std::collections::HashMap<String, String>) -> HeaderMap {
+    let mut map = HeaderMap::new();
+    map.insert(hyper::header::HOST, HeaderValue::from_str(domain).unwrap());
+    map.insert(hyper::header::ACCEPT, HeaderValue::from_static("*/*"));
+    map.insert(hyper::header::CONNECTION, HeaderValue::from_static("close"));
+    map.insert(hyper::header::USER_AGENT, HeaderValue::from_static(USER_AGENT));
+
+    for (name, value) in extra {
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(name.as_bytes()),
+            HeaderValue::from_str(value),
+        ) {
+            map.insert(name, value);
+        }
+    }
+
+    map.insert(
+        hyper::header::ACCEPT_ENCODING,
+        HeaderValue::from_static("identity"),
+    );
+
+    map
+}
+
+/// Estimates the serialized size, in bytes, of the HTTP/1.1 request that
+/// `run_prover` will send, so `max_sent_data` can be sized from the actual
+/// request instead of a fixed guess. Includes the `Content-Length` line
+/// hyper emits for a non-empty body, and a slack margin for framing
+/// overhead the MPC layer adds on top of the raw HTTP bytes.
+fn estimated_request_len(
+    method: &str,
+    uri: &str,
+    domain: &str,
+    headers: &std::collections::HashMap<String, String>,
+    body: Option<&[u8]>,
+) -> usize {
+    let body_len = body.map_or(0, <[u8]>::len);
+
+    let mut len = format!("{method} {uri} HTTP/1.1\r\n").len();
+    for (name, value) in build_headers(domain, headers).iter() {
+        len += name.as_str().len() + ": ".len() + value.len() + "\r\n".len();
+    }
+    if body_len > 0 {
+        len += format!("content-length: {body_len}\r\n").len();
+    }
+    len += "\r\n".len();
+    len += body_len;
+
+    len + len / 4
+}
+
+/// Performs the notary server session handshake and returns the raw duplex
+/// byte stream the MPC-TLS protocol runs over.
+async fn notary_connect(
+    notary_host: &str,
+    notary_port: u16,
+    notary_tls: bool,
+    max_sent_data: usize,
+    max_recv_data: usize,
+) -> Result<Box<dyn NotaryIo>, Box<dyn std::error::Error>> {
+    let session_response: NotarizationSessionResponse = {
+        let socket = tokio::net::TcpStream::connect((notary_host, notary_port)).await?;
+        let io = TokioIo::new(dial_notary(socket, notary_tls, notary_host).await?);
+        let (mut request_sender, connection) = hyper::client::conn::http1::handshake(io).await?;
+        tokio::spawn(connection);
+
+        let payload = serde_json::to_vec(&NotarizationSessionRequest {
+            client_type: ClientType::Tcp,
+            max_sent_data,
+            max_recv_data,
+        })?;
+
+        let request = Request::builder()
+            .uri("/session")
+            .method("POST")
+            .header("Host", notary_host)
+            .header("Content-Type", "application/json")
+            .body(Full::<Bytes>::from(payload))?;
+
+        let response = request_sender.send_request(request).await?;
+        if response.status() != StatusCode::OK {
+            return Err(format!(
+                "notary server rejected the session request: {}",
+                response.status()
+            )
+            .into());
+        }
+
+        let body = response.collect().await?.to_bytes();
+        serde_json::from_slice(&body)?
+    };
+
+    // Reconnect and upgrade the connection into the raw MPC-TLS byte stream
+    // authorized by the session id we were just issued.
+    let socket = tokio::net::TcpStream::connect((notary_host, notary_port)).await?;
+    let io = TokioIo::new(dial_notary(socket, notary_tls, notary_host).await?);
+    let (mut request_sender, connection) = hyper::client::conn::http1::handshake(io).await?;
+    let connection_task = tokio::spawn(connection.with_upgrades());
+
+    let request = Request::builder()
+        .uri(format!(
+            "/notarize?sessionId={}",
+            session_response.session_id
+        ))
+        .method("GET")
+        .header("Host", notary_host)
+        .header("Connection", "Upgrade")
+        .header("Upgrade", "tcp")
+        .body(Empty::<Bytes>::new())?;
+
+    let response = request_sender.send_request(request).await?;
+    if response.status() != StatusCode::SWITCHING_PROTOCOLS {
+        return Err(format!(
+            "notary server refused to upgrade the notarization connection: {}",
+            response.status()
+        )
+        .into());
+    }
+
+    let upgraded = hyper::upgrade::on(response).await?;
+    drop(connection_task);
+
+    Ok(Box::new(TokioIo::new(upgraded)))
+}
+
+/// Dials the TCP connection itself if `notary_tls` is false, or wraps it in a
+/// TLS stream to `notary_host` if true.
+async fn dial_notary(
+    socket: tokio::net::TcpStream,
+    notary_tls: bool,
+    notary_host: &str,
+) -> Result<Box<dyn NotaryIo>, Box<dyn std::error::Error>> {
+    if !notary_tls {
+        return Ok(Box::new(socket));
+    }
+
+    let mut root_store = tokio_rustls::rustls::RootCertStore::empty();
+    root_store.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let tls_config = tokio_rustls::rustls::ClientConfig::builder()
+        .with_root_certificates(root_store)
+        .with_no_client_auth();
+    let connector = tokio_rustls::TlsConnector::from(std::sync::Arc::new(tls_config));
+    let server_name = tokio_rustls::rustls::pki_types::ServerName::try_from(notary_host.to_owned())?;
+
+    Ok(Box::new(connector.connect(server_name, socket).await?))
+}
+
+/// Runs the MPC-TLS prover session against `domain`/`uri` using `notary_socket`
+/// as the connection to the notary, parses and commits the HTTP transcript,
+/// and writes out the resulting attestation, secrets and redacted
+/// presentation.
+#[allow(clippy::too_many_arguments)]
+async fn run_prover(
+    domain: String,
+    uri: String,
+    method: String,
+    headers: std::collections::HashMap<String, String>,
+    body: Option<Vec<u8>>,
+    notary_socket: impl AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    redact: RedactionSpec,
+    defer_decryption: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
     let domain_rs: &str = &domain;
+    let max_sent_data = estimated_request_len(&method, &uri, &domain, &headers, body.as_deref());
+
     // Prover configuration.
     let config = ProverConfig::builder()
         .server_name(domain_rs)
         .protocol_config(
             ProtocolConfig::builder()
-                // We must configure the amount of data we expect to exchange beforehand, which will
-                // be preprocessed prior to the connection. Reducing these limits will improve
-                // performance.
-                .max_sent_data(1024)
-                .max_recv_data(4096)
+                .max_sent_data(max_sent_data)
+                .max_recv_data(PROVER_MAX_RECV_DATA)
                 .build()?,
         )
         .build()?;
 
     // Create a new prover and perform necessary setup.
-    let prover = Prover::new(config).setup(prover_socket.compat()).await?;
+    let prover = Prover::new(config).setup(notary_socket).await?;
 
     // Open a TCP connection to the server.
     let client_socket = tokio::net::TcpStream::connect((domain.clone(), 443)).await?;
@@ -91,6 +468,10 @@ pub async fn prove(domain: String, uri: String) -> Result<(), Box<dyn std::error
     let (mpc_tls_connection, prover_fut) = prover.connect(client_socket.compat()).await?;
     let mpc_tls_connection = TokioIo::new(mpc_tls_connection.compat());
 
+    // Grab a control handle before the future moves onto the background task,
+    // so we can still ask the prover to defer decryption of the response.
+    let prover_ctrl = prover_fut.control();
+
     // Spawn the prover task to be run concurrently in the background.
     let prover_task = tokio::spawn(prover_fut);
 
@@ -101,26 +482,35 @@ pub async fn prove(domain: String, uri: String) -> Result<(), Box<dyn std::error
     // Spawn the HTTP task to be run concurrently in the background.
     tokio::spawn(connection);
 
-    // Build a simple HTTP request with common headers
-    let request = Request::builder()
-        .uri(uri)
-        .header("Host", domain.clone())
-        .header("Accept", "*/*")
-        // Using "identity" instructs the Server not to use compression for its HTTP response.
-        // TLSNotary tooling does not support compression.
-        .header("Accept-Encoding", "identity")
-        .header("Connection", "close")
-        .header("User-Agent", USER_AGENT)
-        .body(Empty::<Bytes>::new())?;
+    if defer_decryption {
+        // Defer MPC decryption of the server's response until after the TLS
+        // session is closed, so the online phase only covers the request
+        // path. The connection below is dropped to close (never manually
+        // half-closed), which this deferred path requires.
+        prover_ctrl.defer_decryption().await?;
+    }
+
+    // Build the HTTP request from the caller-supplied method, headers and
+    // body, on top of the common headers every notarization needs. Caller
+    // headers (e.g. a custom `User-Agent`) replace the defaults rather than
+    // being appended alongside them.
+    let mut request_builder = Request::builder().method(method.as_str()).uri(uri);
+    *request_builder
+        .headers_mut()
+        .expect("request builder has no error yet") = build_headers(&domain, &headers);
+
+    let body = body.unwrap_or_default();
+    let request = request_builder.body(Full::<Bytes>::from(body))?;
 
     println!("Starting an MPC TLS connection with the server");
 
     // Send the request to the server and wait for the response.
     let response = request_sender.send_request(request).await?;
 
-    println!("Got a response from the server");
-
-    assert!(response.status() == StatusCode::OK);
+    // Any status is a legitimate thing to notarize (e.g. POST/PUT APIs
+    // routinely answer 201/204/3xx), so we don't assert a particular one
+    // here; just record what the server actually said.
+    println!("Got a response from the server: {}", response.status());
 
     // The prover task should be done now, so we can await it.
     let prover = prover_task.await??;
@@ -131,7 +521,11 @@ pub async fn prove(domain: String, uri: String) -> Result<(), Box<dyn std::error
     // Parse the HTTP transcript.
     let transcript = HttpTranscript::parse(prover.transcript())?;
 
-    // Commit to the transcript.
+    // Full sent/received bytes, used to locate the ranges named by `redact`.
+    let sent_bytes = prover.transcript().sent().to_vec();
+    let recv_bytes = prover.transcript().received().to_vec();
+
+    // Commit to the entire transcript, regardless of what will be revealed.
     let mut builder = TranscriptCommitConfig::builder(prover.transcript());
 
     DefaultHttpCommitter::default().commit_transcript(&mut builder, &transcript)?;
@@ -143,6 +537,29 @@ pub async fn prove(domain: String, uri: String) -> Result<(), Box<dyn std::error
 
     let (attestation, secrets) = prover.finalize(&config).await?;
 
+    // Reveal every byte except the ranges named by `redact`.
+    let (redacted_sent, redacted_recv) =
+        redacted_ranges(&transcript, &sent_bytes, &recv_bytes, &redact);
+    let revealed_sent = Idx::new(0..sent_bytes.len()).difference(&redacted_sent);
+    let revealed_recv = Idx::new(0..recv_bytes.len()).difference(&redacted_recv);
+
+    let mut proof_builder = secrets.transcript_proof_builder();
+    proof_builder.reveal_sent(&revealed_sent)?;
+    proof_builder.reveal_recv(&revealed_recv)?;
+    let transcript_proof = proof_builder.build()?;
+
+    // Bind the redacted transcript proof, plus the server's identity proof,
+    // to the attestation so the result is a self-contained, independently
+    // verifiable `Presentation`: a verifier who only has this file (and
+    // never sees `secrets`) can check the notary's signature, authenticate
+    // the server name, and read the revealed bytes.
+    let provider = CryptoProvider::default();
+    let presentation = attestation
+        .presentation_builder(&provider)
+        .identity_proof(secrets.identity_proof())
+        .transcript_proof(transcript_proof)
+        .build()?;
+
     // Write the attestation to disk.
     tokio::fs::write(
         "example.attestation.tlsn",
@@ -153,11 +570,102 @@ pub async fn prove(domain: String, uri: String) -> Result<(), Box<dyn std::error
     // Write the secrets to disk.
     tokio::fs::write("example.secrets.tlsn", bincode::serialize(&secrets)?).await?;
 
+    // Write the redacted presentation to disk: a verifier can check this
+    // against the notary's signature without ever seeing the hidden bytes.
+    tokio::fs::write(
+        "example.presentation.tlsn",
+        bincode::serialize(&presentation)?,
+    )
+    .await?;
+
     println!("Notarization completed successfully!");
     println!(
-        "The attestation has been written to `example.attestation.tlsn` and the \
-        corresponding secrets to `example.secrets.tlsn`."
+        "The attestation has been written to `example.attestation.tlsn`, the \
+        corresponding secrets to `example.secrets.tlsn`, and the redacted \
+        presentation to `example.presentation.tlsn`."
     );
 
     Ok(())
 }
+
+/// What a notarization actually proves, as recovered from an attestation and
+/// its secrets: the notary's verifying key (so a caller can pin or check
+/// which notary signed), the authenticated server name, and the revealed
+/// transcript bytes. Bytes in a redacted region come back zeroed, i.e. as a
+/// gap.
+#[derive(Debug)]
+pub struct VerificationResult {
+    pub verifying_key: VerifyingKey,
+    pub server_name: String,
+    pub sent: Vec<u8>,
+    pub received: Vec<u8>,
+}
+
+/// Verifies a notarization produced by [`prove`] or [`prove_with_notary`].
+///
+/// Deserializes the attestation and secrets written to disk, rebuilds a
+/// presentation that reveals everything except the ranges named by `redact`
+/// (pass the same [`RedactionSpec`] used to notarize to see the same
+/// redacted regions come back as gaps), and checks the notary's secp256k1
+/// signature over it using the default [`CryptoProvider`]. The notary's
+/// verifying key embedded in that presentation is returned too: `verify`
+/// only confirms the attestation is internally consistent (the signature
+/// matches the embedded key), so a caller that needs to know a *specific,
+/// trusted* notary signed it must compare `verifying_key` against that
+/// notary's known public key itself.
+///
+/// The secrets file contains the full, unredacted transcript, so this is
+/// meant to be run by the prover to check its own notarization. Do not hand
+/// `secrets_path` to a third-party verifier; give them `example.presentation.tlsn`
+/// (built from a [`RedactionSpec`]) and the attestation instead.
+#[tokio::main]
+pub async fn verify(
+    attestation_path: String,
+    secrets_path: String,
+    redact: RedactionSpec,
+) -> Result<VerificationResult, Box<dyn std::error::Error>> {
+    let attestation: Attestation =
+        bincode::deserialize(&tokio::fs::read(attestation_path).await?)?;
+    let secrets: Secrets = bincode::deserialize(&tokio::fs::read(secrets_path).await?)?;
+
+    let provider = CryptoProvider::default();
+
+    // Rebuild the set of revealed ranges the same way `run_prover` does, so
+    // the same `redact` spec produces the same gaps on both ends.
+    let transcript = HttpTranscript::parse(secrets.transcript())?;
+    let sent_bytes = secrets.transcript().sent().to_vec();
+    let recv_bytes = secrets.transcript().received().to_vec();
+
+    let (redacted_sent, redacted_recv) =
+        redacted_ranges(&transcript, &sent_bytes, &recv_bytes, &redact);
+    let revealed_sent = Idx::new(0..sent_bytes.len()).difference(&redacted_sent);
+    let revealed_recv = Idx::new(0..recv_bytes.len()).difference(&redacted_recv);
+
+    let mut proof_builder = secrets.transcript_proof_builder();
+    proof_builder.reveal_sent(&revealed_sent)?;
+    proof_builder.reveal_recv(&revealed_recv)?;
+    let transcript_proof = proof_builder.build()?;
+
+    let presentation = attestation
+        .presentation_builder(&provider)
+        .identity_proof(secrets.identity_proof())
+        .transcript_proof(transcript_proof)
+        .build()?;
+
+    let verifying_key = presentation.verifying_key().clone();
+    let output = presentation.verify(&provider)?;
+
+    let transcript = output
+        .transcript
+        .ok_or("the verified presentation did not reveal a transcript")?;
+    let server_name = output
+        .server_name
+        .ok_or("the verified presentation did not authenticate a server name")?;
+
+    Ok(VerificationResult {
+        verifying_key,
+        server_name: server_name.to_string(),
+        sent: transcript.sent_unsafe().to_vec(),
+        received: transcript.received_unsafe().to_vec(),
+    })
+}